@@ -0,0 +1,119 @@
+// `/ai <prompt>` assistant: streams a token-by-token reply from an
+// OpenAI-compatible `/chat/completions` endpoint and broadcasts it live to
+// every SSE/WS client, persisting the final text once the stream completes.
+
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use serde::Deserialize;
+
+use crate::{insert_message, AppState, Message, ASSISTANT_AUTHOR};
+
+// Partial (not-yet-persisted) assistant messages are broadcast with this id
+// so they never collide with a real, persisted message id.
+pub const STREAMING_MESSAGE_ID: i64 = -1;
+
+#[derive(Deserialize)]
+struct ChatCompletionChunk {
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChunkChoice {
+    delta: ChunkDelta,
+}
+
+#[derive(Deserialize, Default)]
+struct ChunkDelta {
+    content: Option<String>,
+}
+
+/// Spawns the streaming assistant reply in the background so the caller
+/// (`submit_message`) can return immediately, same as a regular insert.
+pub fn maybe_spawn_assistant_reply(state: AppState, prompt: String) {
+    tokio::spawn(async move {
+        if let Err(e) = stream_assistant_reply(&state, &prompt).await {
+            tracing::error!("Assistant reply error: {}", e);
+        }
+    });
+}
+
+async fn stream_assistant_reply(
+    state: &AppState,
+    prompt: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let base_url = std::env::var("OPENAI_BASE_URL")
+        .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+    let api_key = std::env::var("OPENAI_API_KEY")?;
+    let model = std::env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{base_url}/chat/completions"))
+        .bearer_auth(&api_key)
+        .json(&serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": prompt}],
+            "stream": true,
+        }))
+        .send()
+        .await?;
+
+    let mut stream = response.bytes_stream().eventsource();
+    let mut accumulated = String::new();
+
+    while let Some(event) = stream.next().await {
+        let event = event?;
+
+        if event.data == "[DONE]" {
+            break;
+        }
+
+        let chunk: ChatCompletionChunk = match serde_json::from_str(&event.data) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                tracing::error!("Failed to parse assistant stream chunk: {}", e);
+                continue;
+            }
+        };
+
+        let Some(delta) = chunk
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.delta.content)
+        else {
+            continue;
+        };
+
+        accumulated.push_str(&delta);
+
+        // Broadcast-only: partial replies aren't persisted, so they never
+        // land in `state.messages` and don't need a real id.
+        let _ = state.tx.send(Message {
+            id: STREAMING_MESSAGE_ID,
+            text: accumulated.clone(),
+            created_at: String::new(),
+            author: ASSISTANT_AUTHOR.to_string(),
+            replaces: None,
+        });
+    }
+
+    if !accumulated.is_empty() {
+        if let Some(persisted_id) = insert_message(state, ASSISTANT_AUTHOR, &accumulated).await {
+            // Tell clients the streamed `STREAMING_MESSAGE_ID` bubble is now
+            // this persisted message, so they reconcile the two into one
+            // instead of the change feed's own broadcast of this same row
+            // (which carries `persisted_id` but no `replaces`) rendering as
+            // a second, duplicate bubble.
+            let _ = state.tx.send(Message {
+                id: persisted_id,
+                text: accumulated,
+                created_at: String::new(),
+                author: ASSISTANT_AUTHOR.to_string(),
+                replaces: Some(STREAMING_MESSAGE_ID),
+            });
+        }
+    }
+
+    Ok(())
+}
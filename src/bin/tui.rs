@@ -0,0 +1,292 @@
+// Terminal chat client: consumes the server's `/events` SSE feed and posts
+// new messages to `/messages`, so the demo has a usable non-browser client.
+
+use std::io::{self, Stdout};
+use std::time::Duration;
+
+use crossterm::event::{Event as TermEvent, EventStream, KeyCode, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, ExecutableCommand};
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Terminal;
+use serde::Deserialize;
+
+/// Shape of the JSON payload the server's `/events` SSE feed carries (see
+/// `message_to_event` in `main.rs`).
+#[derive(Deserialize)]
+struct IncomingMessage {
+    id: i64,
+    text: String,
+    #[serde(default)]
+    author: String,
+    // Set on the one-off reconciliation broadcast a streamed `/ai` reply
+    // sends once persisted: the id of the placeholder `id` now replaces.
+    #[serde(default)]
+    replaces: Option<i64>,
+}
+
+/// One rendered line, keyed by `id` so `/ai` streaming updates (which reuse
+/// the same id for every delta) replace in place instead of piling up.
+struct DisplayMessage {
+    id: i64,
+    line: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server_url = std::env::args()
+        .nth(1)
+        .or_else(|| std::env::var("CHAT_SERVER_URL").ok())
+        .unwrap_or_else(|| "http://127.0.0.1:3000".to_string());
+
+    let mut terminal = setup_terminal()?;
+    let result = run(&mut terminal, &server_url).await;
+    restore_terminal(&mut terminal)?;
+
+    result
+}
+
+fn setup_terminal() -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(stdout))
+}
+
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    disable_raw_mode()?;
+    terminal.backend_mut().execute(LeaveAlternateScreen)?;
+    terminal.show_cursor()
+}
+
+async fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    server_url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::new();
+
+    let response = client.get(format!("{server_url}/events")).send().await?;
+    let mut events = response.bytes_stream().eventsource();
+
+    let mut messages: Vec<DisplayMessage> = Vec::new();
+    // Synthetic, strictly-decreasing ids for locally generated notes (parse
+    // errors, connection errors, send failures) so each gets its own line
+    // instead of overwriting the previous one.
+    let mut next_note_id: i64 = -1_000_000;
+    let mut input = String::new();
+    let mut list_state = ListState::default();
+    let mut term_events = EventStream::new();
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+
+    draw(terminal, &messages, &input, &mut list_state)?;
+
+    loop {
+        tokio::select! {
+            maybe_event = events.next() => {
+                match maybe_event {
+                    Some(Ok(event)) => {
+                        match serde_json::from_str::<IncomingMessage>(&event.data) {
+                            Ok(incoming) => upsert_message(&mut messages, incoming),
+                            Err(e) => {
+                                push_note(&mut messages, &mut next_note_id, format!("[malformed event: {e}]"));
+                            }
+                        }
+                        draw(terminal, &messages, &input, &mut list_state)?;
+                    }
+                    Some(Err(e)) => {
+                        push_note(&mut messages, &mut next_note_id, format!("[connection error: {e}]"));
+                        draw(terminal, &messages, &input, &mut list_state)?;
+                    }
+                    None => break,
+                }
+            }
+            maybe_term_event = term_events.next() => {
+                match maybe_term_event {
+                    Some(Ok(TermEvent::Key(key))) => {
+                        if key.modifiers.contains(KeyModifiers::CONTROL)
+                            && matches!(key.code, KeyCode::Char('c') | KeyCode::Char('q'))
+                        {
+                            break;
+                        }
+                        match key.code {
+                            KeyCode::Enter => {
+                                let text = std::mem::take(&mut input);
+                                if !text.trim().is_empty() {
+                                    if let Err(e) = post_message(&client, server_url, &text).await {
+                                        push_note(&mut messages, &mut next_note_id, format!("[send failed: {e}]"));
+                                    }
+                                }
+                            }
+                            KeyCode::Char(c) => input.push(c),
+                            KeyCode::Backspace => {
+                                input.pop();
+                            }
+                            _ => {}
+                        }
+                        draw(terminal, &messages, &input, &mut list_state)?;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                    None => break,
+                }
+            }
+            // Redraw on a tick too, so SSE keepalive comments don't freeze the UI.
+            _ = tick.tick() => {
+                draw(terminal, &messages, &input, &mut list_state)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn upsert_message(messages: &mut Vec<DisplayMessage>, incoming: IncomingMessage) {
+    let line = if incoming.author.is_empty() || incoming.author == "user" {
+        incoming.text
+    } else {
+        format!("[{}] {}", incoming.author, incoming.text)
+    };
+
+    // Once persisted, a streamed reply arrives once more tagged with
+    // `replaces: STREAMING_MESSAGE_ID` - look up the placeholder line by
+    // that id and rename it onto the real id, instead of leaving both on
+    // screen. The change feed then re-delivers the same row under its real
+    // id with no `replaces`, which the lookup below now resolves to this
+    // same line, so it just re-renders in place.
+    let existing_index = messages
+        .iter()
+        .position(|message| message.id == incoming.id)
+        .or_else(|| {
+            let replaces = incoming.replaces?;
+            messages.iter().position(|message| message.id == replaces)
+        });
+
+    if let Some(index) = existing_index {
+        messages[index].id = incoming.id;
+        messages[index].line = line;
+    } else {
+        messages.push(DisplayMessage { id: incoming.id, line });
+    }
+}
+
+fn push_note(messages: &mut Vec<DisplayMessage>, next_note_id: &mut i64, line: String) {
+    messages.push(DisplayMessage {
+        id: *next_note_id,
+        line,
+    });
+    *next_note_id -= 1;
+}
+
+async fn post_message(
+    client: &reqwest::Client,
+    server_url: &str,
+    text: &str,
+) -> Result<(), reqwest::Error> {
+    client
+        .post(format!("{server_url}/messages"))
+        .form(&[("text", text)])
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+fn draw(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    messages: &[DisplayMessage],
+    input: &str,
+    list_state: &mut ListState,
+) -> io::Result<()> {
+    // Always keep the most recent message selected so the list auto-scrolls
+    // to the tail instead of freezing once it overflows the pane height.
+    if !messages.is_empty() {
+        list_state.select(Some(messages.len() - 1));
+    }
+
+    terminal.draw(|frame| {
+        let area = frame.area();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(3)])
+            .split(area);
+
+        let items: Vec<ListItem> = messages
+            .iter()
+            .map(|message| ListItem::new(message.line.as_str()))
+            .collect();
+        let messages_list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Messages"));
+        frame.render_stateful_widget(messages_list, chunks[0], list_state);
+
+        let input_box = Paragraph::new(input)
+            .style(Style::default().fg(Color::White))
+            .block(Block::default().borders(Borders::ALL).title("Message"));
+        frame.render_widget(input_box, chunks[1]);
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(messages: &[DisplayMessage]) -> Vec<i64> {
+        messages.iter().map(|message| message.id).collect()
+    }
+
+    fn incoming(id: i64, text: &str, author: &str, replaces: Option<i64>) -> IncomingMessage {
+        IncomingMessage {
+            id,
+            text: text.to_string(),
+            author: author.to_string(),
+            replaces,
+        }
+    }
+
+    #[test]
+    fn upsert_inserts_new_message() {
+        let mut messages = Vec::new();
+        upsert_message(&mut messages, incoming(1, "hi", "user", None));
+        assert_eq!(ids(&messages), vec![1]);
+        assert_eq!(messages[0].line, "hi");
+    }
+
+    #[test]
+    fn upsert_replaces_existing_message_with_same_id() {
+        let mut messages = vec![DisplayMessage {
+            id: -1,
+            line: "[assistant] partial".to_string(),
+        }];
+        upsert_message(&mut messages, incoming(-1, "partial more", "assistant", None));
+        assert_eq!(ids(&messages), vec![-1]);
+        assert_eq!(messages[0].line, "[assistant] partial more");
+    }
+
+    #[test]
+    fn upsert_reconciles_placeholder_via_replaces() {
+        let mut messages = vec![DisplayMessage {
+            id: -1,
+            line: "[assistant] partial".to_string(),
+        }];
+        upsert_message(&mut messages, incoming(42, "final", "assistant", Some(-1)));
+        assert_eq!(ids(&messages), vec![42]);
+        assert_eq!(messages[0].line, "[assistant] final");
+    }
+
+    #[test]
+    fn push_note_assigns_strictly_decreasing_ids() {
+        let mut messages = Vec::new();
+        let mut next_note_id: i64 = -1_000_000;
+        push_note(&mut messages, &mut next_note_id, "first".to_string());
+        push_note(&mut messages, &mut next_note_id, "second".to_string());
+        assert_eq!(ids(&messages), vec![-1_000_000, -1_000_001]);
+        assert_eq!(messages[0].line, "first");
+        assert_eq!(messages[1].line, "second");
+    }
+}
@@ -0,0 +1,190 @@
+// Pluggable backends for receiving Postgres change notifications.
+//
+// `start_realtime_listener` used to hard-depend on Supabase Realtime. The
+// `ChangeSource` trait lets us swap that out for a plain `LISTEN`/`NOTIFY`
+// connection against any Postgres instance, selected at runtime via
+// `CHANGE_SOURCE`.
+
+use async_trait::async_trait;
+use futures::stream::{poll_fn, BoxStream, StreamExt};
+use tokio::sync::mpsc;
+use tokio_postgres::{AsyncMessage, NoTls};
+
+use crate::{AppState, Message};
+
+/// A source of row-change events that `main` drives in a background task,
+/// independent of how those events actually get to us.
+#[async_trait]
+pub trait ChangeSource {
+    /// Run the source, pushing every observed `Message` into `state`.
+    /// Returns when the underlying connection ends or errors.
+    async fn run(&mut self, state: AppState) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Reads the `CHANGE_SOURCE` env var (`supabase` or `listen`, defaulting to
+/// `supabase`) and builds the matching `ChangeSource`, validating only the
+/// env vars the selected backend actually needs so a missing one fails fast
+/// at startup instead of booting with a silently dead change feed.
+pub async fn build_change_source(
+    database_url: &str,
+) -> Result<Box<dyn ChangeSource + Send>, Box<dyn std::error::Error>> {
+    match std::env::var("CHANGE_SOURCE").as_deref() {
+        Ok("listen") => Ok(Box::new(ListenNotifySource::new(database_url).await?)),
+        Ok("supabase") | Err(_) => {
+            let realtime_url = std::env::var("SUPABASE_REALTIME_URL")?;
+            let api_key = std::env::var("SUPABASE_API_KEY")?;
+            Ok(Box::new(SupabaseChangeSource::new(&realtime_url, &api_key)))
+        }
+        Ok(other) => Err(format!("unknown CHANGE_SOURCE: {other}").into()),
+    }
+}
+
+/// The existing Supabase Realtime backend, unchanged in behavior, just moved
+/// behind the `ChangeSource` trait.
+pub struct SupabaseChangeSource {
+    realtime_url: String,
+    api_key: String,
+}
+
+impl SupabaseChangeSource {
+    pub fn new(realtime_url: &str, api_key: &str) -> Self {
+        Self {
+            realtime_url: realtime_url.to_string(),
+            api_key: api_key.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChangeSource for SupabaseChangeSource {
+    async fn run(&mut self, state: AppState) -> Result<(), Box<dyn std::error::Error>> {
+        use supabase_client_rs::supabase_realtime_rs::{
+            PostgresChangeEvent, PostgresChangesFilter, PostgresChangesPayload, RealtimeClient,
+            RealtimeClientOptions,
+        };
+
+        tracing::info!("Starting Supabase Realtime listener...");
+
+        let client = RealtimeClient::new(
+            &self.realtime_url,
+            RealtimeClientOptions {
+                api_key: self.api_key.clone(),
+                ..Default::default()
+            },
+        )?;
+        client.connect().await?;
+
+        let channel = client.channel("chat-changes", Default::default()).await;
+
+        let mut rx = channel
+            .on_postgres_changes(
+                PostgresChangesFilter::new(PostgresChangeEvent::Insert, "public")
+                    .table("chat_public_demo"),
+            )
+            .await;
+
+        channel.subscribe().await?;
+        tracing::info!("Subscribed to chat_public_demo changes");
+
+        while let Some(payload) = rx.recv().await {
+            tracing::info!("Received Postgres change: {:?}", payload);
+
+            if let PostgresChangesPayload::Insert(insert_payload) = payload {
+                let message = match serde_json::to_value(&insert_payload.new) {
+                    Ok(value) => match serde_json::from_value::<Message>(value) {
+                        Ok(msg) => msg,
+                        Err(e) => {
+                            tracing::error!("Failed to deserialize message from payload: {}", e);
+                            continue;
+                        }
+                    },
+                    Err(e) => {
+                        tracing::error!("Failed to convert HashMap to JSON value: {}", e);
+                        continue;
+                    }
+                };
+
+                push_message(&state, message).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Backend that `LISTEN`s on a dedicated Postgres connection and decodes
+/// `pg_notify('chat_changes', row_to_json(NEW)::text)` payloads, so the demo
+/// works against any plain Postgres without Supabase.
+pub struct ListenNotifySource {
+    database_url: String,
+}
+
+impl ListenNotifySource {
+    pub async fn new(database_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            database_url: database_url.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl ChangeSource for ListenNotifySource {
+    async fn run(&mut self, state: AppState) -> Result<(), Box<dyn std::error::Error>> {
+        tracing::info!("Starting Postgres LISTEN/NOTIFY listener...");
+
+        let (client, mut connection) = tokio_postgres::connect(&self.database_url, NoTls).await?;
+
+        // `Client` only makes progress while `Connection` is being polled, so
+        // the connection must be driven concurrently with issuing LISTEN
+        // below, not after - otherwise `batch_execute` never resolves.
+        let (notification_tx, mut notification_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut notifications: BoxStream<'_, Result<AsyncMessage, tokio_postgres::Error>> =
+                poll_fn(move |cx| connection.poll_message(cx)).boxed();
+
+            while let Some(item) = notifications.next().await {
+                match item {
+                    Ok(AsyncMessage::Notification(notification)) => {
+                        if notification_tx.send(notification).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::error!("Postgres connection error: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        client.batch_execute("LISTEN chat_changes").await?;
+        tracing::info!("Listening on chat_changes channel");
+
+        while let Some(notification) = notification_rx.recv().await {
+            let message = match serde_json::from_str::<Message>(notification.payload()) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    tracing::error!("Failed to deserialize notification payload: {}", e);
+                    continue;
+                }
+            };
+
+            push_message(&state, message).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Shared by every `ChangeSource`: record the message and fan it out to SSE
+/// clients exactly like the original Supabase-only code did.
+async fn push_message(state: &AppState, message: Message) {
+    tracing::info!("New message: {:?}", message);
+
+    let mut messages = state.messages.write().await;
+    messages.push(message.clone());
+    drop(messages);
+
+    let _ = state.tx.send(message);
+}
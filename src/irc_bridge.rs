@@ -0,0 +1,120 @@
+// Optional IRC bridge: mirrors the chat room to an IRC channel and back.
+// Enabled by setting IRC_BRIDGE_ENABLED=1; all-or-nothing like the other
+// env-var-selected subsystems (see `change_source`).
+
+use futures::StreamExt;
+use irc::client::prelude::{Client, Command, Config};
+use irc::proto::Message as IrcMessage;
+use tokio::sync::broadcast;
+
+use crate::{insert_message, AppState};
+
+// Author prefix stamped on messages that originated on IRC. Inserting them
+// through the normal `insert_message` path lets them flow back to web
+// clients via the change feed, but the outbound task below filters them out
+// so they aren't echoed straight back to IRC.
+const IRC_AUTHOR_PREFIX: &str = "irc:";
+
+pub fn is_enabled() -> bool {
+    std::env::var("IRC_BRIDGE_ENABLED").as_deref() == Ok("1")
+}
+
+/// Spawns the bridge in the background if `IRC_BRIDGE_ENABLED=1`.
+pub fn maybe_spawn(state: AppState) {
+    if !is_enabled() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(e) = run(state).await {
+            tracing::error!("IRC bridge error: {}", e);
+        }
+    });
+}
+
+async fn run(state: AppState) -> Result<(), Box<dyn std::error::Error>> {
+    let server = std::env::var("IRC_SERVER")?;
+    let channel = std::env::var("IRC_CHANNEL")?;
+    let nickname = std::env::var("IRC_NICKNAME").unwrap_or_else(|_| "chat-demo-bridge".to_string());
+    let port = std::env::var("IRC_PORT")
+        .ok()
+        .and_then(|value| value.parse().ok());
+
+    let config = Config {
+        nickname: Some(nickname),
+        server: Some(server),
+        port,
+        channels: vec![channel.clone()],
+        use_tls: Some(port == Some(6697)),
+        ..Default::default()
+    };
+
+    let client = Client::from_config(config).await?;
+    client.identify()?;
+    tracing::info!("IRC bridge connected, joined {}", channel);
+
+    let sender = client.sender();
+    let rx = state.tx.subscribe();
+
+    tokio::select! {
+        result = forward_to_irc(rx, sender, channel) => result,
+        result = forward_to_chat(client, state) => result,
+    }
+}
+
+/// web chat -> IRC: mirror every non-bridge-originated message as a PRIVMSG.
+async fn forward_to_irc(
+    mut rx: broadcast::Receiver<crate::Message>,
+    sender: irc::client::Sender,
+    channel: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        match rx.recv().await {
+            Ok(message) => {
+                // Skip bridge-originated messages (echo guard) and
+                // not-yet-persisted `/ai` streaming partials (see
+                // `ai::STREAMING_MESSAGE_ID`) — otherwise a single reply
+                // turns into one ever-growing PRIVMSG per token. Also skip
+                // the `replaces` reconciliation broadcast: the change feed
+                // delivers that same persisted row again on its own.
+                if message.author.starts_with(IRC_AUTHOR_PREFIX)
+                    || message.id <= 0
+                    || message.replaces.is_some()
+                {
+                    continue;
+                }
+
+                sender.send_privmsg(&channel, format!("[{}] {}", message.author, message.text))?;
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("IRC bridge lagged, skipped {} messages", skipped);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// IRC -> web chat: insert incoming PRIVMSGs through the normal DB path so
+/// they flow back to all web clients via the change feed.
+async fn forward_to_chat(
+    mut client: Client,
+    state: AppState,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stream = client.stream()?;
+
+    while let Some(message) = stream.next().await.transpose()? {
+        if let IrcMessage {
+            command: Command::PRIVMSG(_, text),
+            ..
+        } = &message
+        {
+            let nick = message.source_nickname().unwrap_or("unknown").to_string();
+            insert_message(&state, &format!("{IRC_AUTHOR_PREFIX}{nick}"), text).await;
+        }
+    }
+
+    Ok(())
+}
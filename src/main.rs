@@ -1,29 +1,64 @@
 use axum::{
     Form, Router,
     extract::State,
+    http::HeaderMap,
     response::{Html, IntoResponse, Sse},
     routing::{get, post},
 };
-use futures::stream::{self, Stream};
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::{convert::Infallible, sync::Arc, time::Duration};
-use supabase_client_rs::supabase_realtime_rs::{
-    PostgresChangeEvent, PostgresChangesFilter, PostgresChangesPayload, RealtimeClient,
-    RealtimeClientOptions,
-};
 use tokio::sync::{RwLock, broadcast};
 use tokio_postgres::{Client, NoTls};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod ai;
+mod change_source;
+mod irc_bridge;
+mod ws;
+
+use ai::maybe_spawn_assistant_reply;
+use change_source::build_change_source;
+use ws::ws_handler;
+
+// Author stamped on messages generated by the `/ai` assistant, so the
+// front-end can style them distinctly from regular user messages.
+const ASSISTANT_AUTHOR: &str = "assistant";
+
 // Message structure matching our Postgres table
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Message {
     id: i64,
     text: String,
     created_at: String,
+    #[serde(default = "default_author")]
+    author: String,
+    // Set only on the one-off reconciliation broadcast a streaming `/ai`
+    // reply sends once persisted: the id of the not-yet-persisted
+    // placeholder this message's `id` now replaces. See
+    // `ai::stream_assistant_reply`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replaces: Option<i64>,
+}
+
+fn default_author() -> String {
+    "user".to_string()
+}
+
+// Shared by `sse_handler` and `ws::handle_socket`: both subscribe to
+// `state.tx` before snapshotting history, so their live stream may re-deliver
+// a persisted message (id > 0) already covered by that snapshot. `/ai`
+// streaming partials use a fixed non-positive id and must always pass
+// through, or a reconnecting client would stop seeing them.
+pub(crate) fn is_already_replayed(message_id: i64, baseline_id: i64) -> bool {
+    message_id > 0 && message_id <= baseline_id
 }
 
+// How many recent messages a freshly connected SSE client is replayed with,
+// unless overridden by SSE_HISTORY_LIMIT.
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
 // Form data for submitting messages
 #[derive(Deserialize)]
 struct MessageForm {
@@ -37,8 +72,6 @@ struct MessageForm {
 #[derive(Clone)]
 struct AppState {
     messages: Arc<RwLock<Vec<Message>>>,
-    realtime_url: String,
-    api_key: String,
     db_client: Arc<RwLock<Client>>,
     tx: broadcast::Sender<Message>,
 }
@@ -56,8 +89,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load environment variables
     dotenvy::dotenv().ok();
-    let realtime_url = std::env::var("SUPABASE_REALTIME_URL")?;
-    let api_key = std::env::var("SUPABASE_API_KEY")?;
     let database_url = std::env::var("DATABASE_URL")?;
 
     // Connect to Postgres
@@ -78,7 +109,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let messages_as_row = client
         .query(
-            "SELECT id, text, created_at::text FROM chat_public_demo ORDER BY created_at ASC",
+            "SELECT id, text, created_at::text, author FROM chat_public_demo ORDER BY created_at ASC",
             &[],
         )
         .await?;
@@ -88,6 +119,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             id: row.get(0),
             text: row.get(1),
             created_at: row.get(2),
+            author: row.get(3),
+            replaces: None,
         })
         .collect();
 
@@ -95,25 +128,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize shared state
     let state = AppState {
         messages: Arc::new(RwLock::new(messages)),
-        realtime_url: realtime_url.clone(),
-        api_key: api_key.clone(),
         db_client: Arc::new(RwLock::new(client)),
         tx,
     };
 
-    // Spawn Realtime listener in background task
+    // Build the configured change source (Supabase Realtime or a plain
+    // Postgres LISTEN/NOTIFY connection) and drive it in the background.
+    let mut change_source = build_change_source(&database_url).await?;
     let state_clone = state.clone();
     tokio::spawn(async move {
-        if let Err(e) = start_realtime_listener(state_clone).await {
-            tracing::error!("Realtime listener error: {}", e);
+        if let Err(e) = change_source.run(state_clone).await {
+            tracing::error!("Change source error: {}", e);
         }
     });
 
+    // Optionally bridge the room to an IRC channel (IRC_BRIDGE_ENABLED=1)
+    irc_bridge::maybe_spawn(state.clone());
+
     // Build Axum router
     let app = Router::new()
         .route("/", get(index_handler))
         .route("/messages", post(submit_message))
         .route("/events", get(sse_handler))
+        .route("/ws", get(ws_handler))
         .layer(TraceLayer::new_for_http())
         .with_state(state);
 
@@ -126,77 +163,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-// Start Realtime client and listen for Postgres changes
-async fn start_realtime_listener(state: AppState) -> Result<(), Box<dyn std::error::Error>> {
-    tracing::info!("Starting Realtime listener...");
-
-    // Connect to Supabase Realtime
-    let client = RealtimeClient::new(
-        &state.realtime_url,
-        RealtimeClientOptions {
-            api_key: state.api_key.clone(),
-            ..Default::default()
-        },
-    )?;
-    client.connect().await?;
-
-    // Subscribe to chat_public_demo table changes
-    let channel = client.channel("chat-changes", Default::default()).await;
-
-    let mut rx = channel
-        .on_postgres_changes(
-            PostgresChangesFilter::new(PostgresChangeEvent::Insert, "public")
-                .table("chat_public_demo"),
-        )
-        .await;
-
-    channel.subscribe().await?;
-    tracing::info!("Subscribed to chat_public_demo changes");
-
-    // Listen for new messages
-    while let Some(payload) = rx.recv().await {
-        tracing::info!("Received Postgres change: {:?}", payload);
-
-        match payload {
-            PostgresChangesPayload::Insert(insert_payload) => {
-                // Convert HashMap to JSON Value, then deserialize to Message struct
-                let message = match serde_json::to_value(&insert_payload.new) {
-                    Ok(value) => match serde_json::from_value::<Message>(value) {
-                        Ok(msg) => msg,
-                        Err(e) => {
-                            tracing::error!("Failed to deserialize message from payload: {}", e);
-                            continue;
-                        }
-                    },
-                    Err(e) => {
-                        tracing::error!("Failed to convert HashMap to JSON value: {}", e);
-                        continue;
-                    }
-                };
-
-                tracing::info!("New message: {:?}", message);
-
-                // Add to shared state
-                let mut messages = state.messages.write().await;
-                messages.push(message.clone());
-
-                // Broadcast to all SSE clients
-                let _ = state.tx.send(message);
-            }
-            _ => {}
-        }
-    }
-
-    Ok(())
-}
-
 // Handler for main page
 async fn index_handler(State(state): State<AppState>) -> impl IntoResponse {
     let messages = state.messages.read().await;
 
     let messages_html: String = messages
         .iter()
-        .map(|msg| format!("<div class='message'>{}</div>", msg.text))
+        .map(|msg| {
+            format!(
+                "<div class='message {}' data-message-id='{}'>{}</div>",
+                msg.author, msg.id, msg.text
+            )
+        })
         .collect();
 
     Html(format!(
@@ -233,6 +211,10 @@ async fn index_handler(State(state): State<AppState>) -> impl IntoResponse {
             background: #f0f0f0;
             border-radius: 4px;
         }}
+        .message.assistant {{
+            background: #e0ecff;
+            font-style: italic;
+        }}
         form {{
             display: flex;
             gap: 10px;
@@ -272,10 +254,34 @@ async fn index_handler(State(state): State<AppState>) -> impl IntoResponse {
 
         evtSource.addEventListener('message', function(event) {{
             const messagesDiv = document.getElementById('messages');
-            const messageDiv = document.createElement('div');
-            messageDiv.className = 'message';
-            messageDiv.textContent = event.data;
-            messagesDiv.appendChild(messageDiv);
+            const payload = JSON.parse(event.data);
+
+            // Assistant replies stream multiple updates under the same id
+            // (see `STREAMING_MESSAGE_ID`) - replace the existing div in
+            // place instead of appending a new one for every token.
+            let messageDiv = messagesDiv.querySelector(
+                `[data-message-id="${{payload.id}}"]`
+            );
+
+            // Once persisted, a streamed reply arrives once more tagged
+            // `replaces: STREAMING_MESSAGE_ID` - rename its placeholder div
+            // onto the real id instead of leaving both on screen. The
+            // change feed then re-delivers the same row under its real id
+            // with no `replaces`, which the lookup above now resolves to
+            // this same div, so it just re-renders in place.
+            if (!messageDiv && payload.replaces !== undefined && payload.replaces !== null) {{
+                messageDiv = messagesDiv.querySelector(
+                    `[data-message-id="${{payload.replaces}}"]`
+                );
+            }}
+
+            if (!messageDiv) {{
+                messageDiv = document.createElement('div');
+                messagesDiv.appendChild(messageDiv);
+            }}
+            messageDiv.dataset.messageId = payload.id;
+            messageDiv.className = 'message ' + payload.author;
+            messageDiv.textContent = payload.text;
             messagesDiv.scrollTop = messagesDiv.scrollHeight;
         }});
 
@@ -320,48 +326,185 @@ async fn submit_message(
 ) -> impl IntoResponse {
     tracing::info!("Received message: {}", form.text);
 
-    // Insert message into Postgres
+    // Always persist the user's own message first, so the room shows the
+    // question even when it also triggers an assistant reply below.
+    insert_message(&state, "user", &form.text).await;
+
+    match parse_ai_prompt(&form.text) {
+        Some(prompt) if !prompt.trim().is_empty() => {
+            maybe_spawn_assistant_reply(state, prompt.trim().to_string());
+        }
+        Some(_) => tracing::warn!("Received /ai with no prompt, skipping assistant reply"),
+        None => {}
+    }
+
+    // Return 204 No Content - don't redirect, let SSE update the UI
+    axum::http::StatusCode::NO_CONTENT
+}
+
+// Recognizes the `/ai <prompt>` command in a submitted message, split out of
+// `submit_message` so its prefix/whitespace handling is unit testable
+// without going through Postgres. Returns `None` if `text` isn't an `/ai`
+// command, or `Some(prompt)` (not yet trimmed of inner whitespace) if it is.
+fn parse_ai_prompt(text: &str) -> Option<&str> {
+    let trimmed = text.trim();
+    if trimmed == "/ai" {
+        Some("")
+    } else {
+        trimmed.strip_prefix("/ai ")
+    }
+}
+
+// Inserts a message into Postgres. The change feed (Supabase Realtime or
+// LISTEN/NOTIFY, see `change_source`) picks it up from there and fans it out
+// to `state.messages` + `state.tx`, so callers don't update those directly.
+// Returns the new row's id on success, so callers that need to reconcile a
+// not-yet-persisted placeholder (see `ai::stream_assistant_reply`) can do so.
+async fn insert_message(state: &AppState, author: &str, text: &str) -> Option<i64> {
     let client = state.db_client.read().await;
     match client
-        .execute(
-            "INSERT INTO chat_public_demo (text) VALUES ($1)",
-            &[&form.text],
+        .query_one(
+            "INSERT INTO chat_public_demo (text, author) VALUES ($1, $2) RETURNING id",
+            &[&text, &author],
         )
         .await
     {
-        Ok(_) => tracing::info!("Message inserted successfully"),
-        Err(e) => tracing::error!("Failed to insert message: {}", e),
+        Ok(row) => {
+            tracing::info!("Message inserted successfully");
+            Some(row.get(0))
+        }
+        Err(e) => {
+            tracing::error!("Failed to insert message: {}", e);
+            None
+        }
     }
-
-    // Return 204 No Content - don't redirect, let SSE update the UI
-    axum::http::StatusCode::NO_CONTENT
 }
 
 // Server-Sent Events handler for live updates
 async fn sse_handler(
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>> {
-    // Subscribe to broadcast channel
+    // Browsers set this on reconnect to the `id` of the last event they saw,
+    // so we only need to replay the gap instead of the whole history buffer.
+    let last_event_id: Option<i64> = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+
+    let history_limit: usize = std::env::var("SSE_HISTORY_LIMIT")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_HISTORY_LIMIT);
+
+    // Subscribe *before* snapshotting history: if we snapshotted first, a
+    // message inserted between the snapshot and the subscribe call would be
+    // in neither and would be silently dropped - exactly the gap this
+    // handler is meant to close.
     let rx = state.tx.subscribe();
 
-    let stream = stream::unfold(rx, |mut rx| async move {
-        // Wait for new messages from the broadcast channel
-        match rx.recv().await {
-            Ok(message) => {
-                let event = axum::response::sse::Event::default().data(message.text);
-                Some((Ok(event), rx))
-            }
-            Err(_) => {
-                // Channel closed or lagged, keep connection alive
-                let event = axum::response::sse::Event::default().comment("keepalive");
-                Some((Ok(event), rx))
+    // Snapshot the last `history_limit` messages under the lock, then drop it
+    // before we start streaming so we don't hold it for the connection's life.
+    let history: Vec<Message> = {
+        let messages = state.messages.read().await;
+        let start = messages.len().saturating_sub(history_limit);
+        messages[start..].to_vec()
+    };
+
+    // Subscribing first means the live stream may now also carry messages
+    // already present in the snapshot above; dedup by skipping any persisted
+    // message at or below the highest id we're about to replay from history.
+    let baseline_id = history.last().map(|message| message.id).unwrap_or(i64::MIN);
+
+    let history_stream = stream::iter(
+        history
+            .into_iter()
+            .filter(move |message| last_event_id.is_none_or(|id| message.id > id))
+            .map(|message| Ok(message_to_event(&message))),
+    );
+
+    let live_stream = stream::unfold(rx, move |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(message) if is_already_replayed(message.id, baseline_id) => continue,
+                Ok(message) => return Some((Ok(message_to_event(&message)), rx)),
+                Err(_) => {
+                    // Channel closed or lagged, keep connection alive
+                    let event = axum::response::sse::Event::default().comment("keepalive");
+                    return Some((Ok(event), rx));
+                }
             }
         }
     });
 
+    let stream = history_stream.chain(live_stream);
+
     Sse::new(stream).keep_alive(
         axum::response::sse::KeepAlive::new()
             .interval(Duration::from_secs(15))
             .text("keepalive"),
     )
 }
+
+// Builds the SSE event for a message, stamping it with a stable `id` so
+// `EventSource` can resume via `Last-Event-ID` after a reconnect. The data
+// payload is JSON (not just the text) so the front-end also gets `author`
+// and can replace-by-id for streaming `/ai` updates instead of appending a
+// new div per delta.
+//
+// Only persisted messages (id > 0) get a `.id(...)`. `/ai` streaming
+// partials use a fixed non-positive id (see `ai::STREAMING_MESSAGE_ID`); if
+// we stamped that as the SSE id, it would become the browser's
+// `lastEventId` and every reconnect would send `Last-Event-ID: -1`, which
+// the history filter (`id > last_event_id`) treats as "replay everything".
+fn message_to_event(message: &Message) -> axum::response::sse::Event {
+    let event = axum::response::sse::Event::default();
+    let event = if message.id > 0 {
+        event.id(message.id.to_string())
+    } else {
+        event
+    };
+
+    event.json_data(message).expect("Message always serializes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ai_prompt_bare_command_has_empty_prompt() {
+        assert_eq!(parse_ai_prompt("/ai"), Some(""));
+    }
+
+    #[test]
+    fn parse_ai_prompt_trailing_space_has_empty_prompt() {
+        assert_eq!(parse_ai_prompt("/ai "), Some(""));
+    }
+
+    #[test]
+    fn parse_ai_prompt_extra_whitespace_keeps_prompt() {
+        assert_eq!(parse_ai_prompt("/ai  x"), Some(" x"));
+    }
+
+    #[test]
+    fn parse_ai_prompt_non_command_is_none() {
+        assert_eq!(parse_ai_prompt("hello"), None);
+    }
+
+    #[test]
+    fn is_already_replayed_skips_persisted_messages_within_baseline() {
+        assert!(is_already_replayed(5, 10));
+        assert!(is_already_replayed(10, 10));
+    }
+
+    #[test]
+    fn is_already_replayed_passes_through_beyond_baseline() {
+        assert!(!is_already_replayed(11, 10));
+    }
+
+    #[test]
+    fn is_already_replayed_always_passes_through_streaming_partials() {
+        assert!(!is_already_replayed(-1, 10));
+    }
+}
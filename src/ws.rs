@@ -0,0 +1,95 @@
+// WebSocket endpoint giving clients a single full-duplex channel instead of
+// the SSE (read) + POST /messages (write) split.
+
+use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::IntoResponse;
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+
+use crate::{insert_message, is_already_replayed, AppState, Message};
+
+/// Inbound frame shape: `{ "text": "..." }`.
+#[derive(Deserialize)]
+struct IncomingMessage {
+    text: String,
+}
+
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+async fn handle_socket(socket: WebSocket, state: AppState) {
+    let (mut sender, mut receiver) = socket.split();
+
+    // Subscribe *before* snapshotting history: if we snapshotted first, a
+    // message inserted between the snapshot and the subscribe call would be
+    // in neither and would be silently dropped.
+    let mut rx = state.tx.subscribe();
+
+    // Replay current history so a freshly connected client starts in sync,
+    // same as the SSE handler's initial payload.
+    let history = state.messages.read().await.clone();
+    let baseline_id = history.last().map(|message| message.id).unwrap_or(i64::MIN);
+    for message in &history {
+        if send_message(&mut sender, message).await.is_err() {
+            return;
+        }
+    }
+
+    // One task pumps broadcast messages to the socket, the other pumps
+    // inbound socket frames into Postgres; whichever ends first wins.
+    let mut send_task = tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                // Subscribing before the snapshot means the live stream may
+                // now also carry messages already replayed above - skip
+                // those instead of sending a duplicate.
+                Ok(message) if is_already_replayed(message.id, baseline_id) => continue,
+                Ok(message) => {
+                    if send_message(&mut sender, &message).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("WebSocket receiver lagged, skipped {} messages", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    let state_clone = state.clone();
+    let mut recv_task = tokio::spawn(async move {
+        while let Some(Ok(frame)) = receiver.next().await {
+            match frame {
+                WsMessage::Text(text) => match serde_json::from_str::<IncomingMessage>(&text) {
+                    Ok(incoming) => {
+                        insert_message(&state_clone, "user", &incoming.text).await;
+                    }
+                    Err(e) => tracing::error!("Failed to parse inbound WS frame: {}", e),
+                },
+                WsMessage::Close(_) => break,
+                _ => {}
+            }
+        }
+    });
+
+    tokio::select! {
+        _ = &mut send_task => recv_task.abort(),
+        _ = &mut recv_task => send_task.abort(),
+    }
+}
+
+async fn send_message(
+    sender: &mut futures::stream::SplitSink<WebSocket, WsMessage>,
+    message: &Message,
+) -> Result<(), axum::Error> {
+    let payload = serde_json::to_string(message).expect("Message always serializes");
+    sender.send(WsMessage::Text(payload)).await
+}